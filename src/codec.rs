@@ -0,0 +1,512 @@
+//! The msgpack wire codec described by the inline comments on [`Type`].
+//!
+//! `Struct`/`Tuple` values are msgpack arrays with each member placed at its
+//! `StructContent.index`; `Enum` values are length-2 msgpack arrays of
+//! `[literal index, content value]`. This module turns that description into
+//! working `encode`/`decode` code.
+
+use crate::*;
+use base64::Engine as _;
+use std::collections::HashMap;
+use std::io::{Cursor, Read};
+
+/// Errors that can occur while encoding or decoding a value against a
+/// [`Type`].
+#[derive(Debug, Display, From)]
+pub enum CodecError {
+    /// The `serde_json::Value` being encoded did not have the shape the
+    /// `Type` requires (e.g. a string where a number was expected).
+    #[display(fmt = "value at `{path}` does not match type: {reason}")]
+    #[from(ignore)]
+    ValueMismatch {
+        /// dotted path to the offending value
+        path: String,
+        /// human readable description of the mismatch
+        reason: String,
+    },
+
+    /// A `Struct` or `Tuple` was missing a required (non-`Optional`)
+    /// member.
+    #[display(fmt = "missing required field `{path}`")]
+    #[from(ignore)]
+    MissingField {
+        /// dotted path to the missing field
+        path: String,
+    },
+
+    /// A `NamedType` referenced a name that isn't present in the spec's
+    /// `types` map.
+    #[display(fmt = "unknown type `{name}` referenced at `{path}`")]
+    #[from(ignore)]
+    UnknownType {
+        /// dotted path to the offending reference
+        path: String,
+        /// the type name that could not be resolved
+        name: String,
+    },
+
+    /// Underlying msgpack encoding failure.
+    #[display(fmt = "msgpack encode error: {_0}")]
+    Encode(rmp::encode::ValueWriteError),
+
+    /// Underlying I/O failure from the handful of `rmp::encode` writers
+    /// (`write_nil`/`write_bool`) that report `std::io::Error` instead of
+    /// `ValueWriteError`.
+    #[display(fmt = "msgpack write error: {_0}")]
+    Io(std::io::Error),
+
+    /// Underlying msgpack decoding failure.
+    #[display(fmt = "msgpack decode error: {_0}")]
+    #[from(ignore)]
+    Decode(String),
+}
+
+impl std::error::Error for CodecError {}
+
+impl<E: std::fmt::Debug + rmp::decode::RmpReadErr> From<rmp::decode::ValueReadError<E>>
+    for CodecError
+{
+    fn from(e: rmp::decode::ValueReadError<E>) -> Self {
+        Self::Decode(format!("{e:?}"))
+    }
+}
+
+impl<E: std::fmt::Debug + rmp::decode::RmpReadErr> From<rmp::decode::NumValueReadError<E>>
+    for CodecError
+{
+    fn from(e: rmp::decode::NumValueReadError<E>) -> Self {
+        Self::Decode(format!("{e:?}"))
+    }
+}
+
+impl<E: std::fmt::Debug + rmp::decode::RmpReadErr> From<rmp::decode::MarkerReadError<E>>
+    for CodecError
+{
+    fn from(e: rmp::decode::MarkerReadError<E>) -> Self {
+        Self::Decode(format!("{e:?}"))
+    }
+}
+
+impl<E: std::fmt::Debug + rmp::decode::RmpReadErr> From<rmp::decode::DecodeStringError<'_, E>>
+    for CodecError
+{
+    fn from(e: rmp::decode::DecodeStringError<E>) -> Self {
+        Self::Decode(format!("{e:?}"))
+    }
+}
+
+fn mismatch(path: &str, reason: &str) -> CodecError {
+    CodecError::ValueMismatch {
+        path: path.to_string(),
+        reason: reason.to_string(),
+    }
+}
+
+/// Peek at the next msgpack value in `cur` without consuming it, returning
+/// `true` if it is the nil marker.
+fn peek_is_nil(cur: &Cursor<&[u8]>) -> bool {
+    let buf = *cur.get_ref();
+    let pos = cur.position() as usize;
+    buf.get(pos) == Some(&0xc0)
+}
+
+impl Type {
+    /// Resolve this type through any `NamedType` indirection against
+    /// `types`.
+    fn resolve<'a>(
+        &'a self,
+        path: &str,
+        types: &'a IndexMap<String, Type>,
+    ) -> Result<&'a Type, CodecError> {
+        match self {
+            Type::NamedType { content, .. } => {
+                let next = types.get(content).ok_or_else(|| CodecError::UnknownType {
+                    path: path.to_string(),
+                    name: content.clone(),
+                })?;
+                next.resolve(path, types)
+            }
+            other => Ok(other),
+        }
+    }
+
+    /// Encode `value` into its msgpack wire representation according to this
+    /// `Type`, resolving any `NamedType` references against `types`.
+    pub fn encode_value(
+        &self,
+        types: &IndexMap<String, Type>,
+        value: &serde_json::Value,
+    ) -> Result<Vec<u8>, CodecError> {
+        let mut buf = Vec::new();
+        self.encode_into("$", types, value, &mut buf)?;
+        Ok(buf)
+    }
+
+    /// Decode a msgpack-encoded value according to this `Type`, resolving
+    /// any `NamedType` references against `types`.
+    pub fn decode_value(
+        &self,
+        types: &IndexMap<String, Type>,
+        bytes: &[u8],
+    ) -> Result<serde_json::Value, CodecError> {
+        let mut cur = Cursor::new(bytes);
+        self.decode_from("$", types, &mut cur)
+    }
+
+    fn encode_into(
+        &self,
+        path: &str,
+        types: &IndexMap<String, Type>,
+        value: &serde_json::Value,
+        buf: &mut Vec<u8>,
+    ) -> Result<(), CodecError> {
+        let ty = self.resolve(path, types)?;
+        match ty {
+            Type::Null { .. } => {
+                rmp::encode::write_nil(buf)?;
+            }
+            Type::Bool { .. } => {
+                let v = value.as_bool().ok_or_else(|| mismatch(path, "expected bool"))?;
+                rmp::encode::write_bool(buf, v)?;
+            }
+            Type::I32 { .. } | Type::I64 { .. } => {
+                let v = value.as_i64().ok_or_else(|| mismatch(path, "expected integer"))?;
+                rmp::encode::write_sint(buf, v)?;
+            }
+            Type::U32 { .. } | Type::U64 { .. } => {
+                let v = value
+                    .as_u64()
+                    .ok_or_else(|| mismatch(path, "expected unsigned integer"))?;
+                rmp::encode::write_uint(buf, v)?;
+            }
+            Type::F64 { .. } => {
+                let v = value.as_f64().ok_or_else(|| mismatch(path, "expected number"))?;
+                rmp::encode::write_f64(buf, v)?;
+            }
+            Type::String { .. } => {
+                let v = value.as_str().ok_or_else(|| mismatch(path, "expected string"))?;
+                rmp::encode::write_str(buf, v)?;
+            }
+            Type::Bytes { .. } => {
+                let b64 = value
+                    .as_str()
+                    .ok_or_else(|| mismatch(path, "expected base64 string"))?;
+                let raw = base64_decode(b64).ok_or_else(|| mismatch(path, "invalid base64"))?;
+                rmp::encode::write_bin(buf, &raw)?;
+            }
+            Type::Optional { content, .. } => {
+                if value.is_null() {
+                    rmp::encode::write_nil(buf)?;
+                } else {
+                    content.encode_into(path, types, value, buf)?;
+                }
+            }
+            Type::Array { content, .. } => {
+                let items = value.as_array().ok_or_else(|| mismatch(path, "expected array"))?;
+                rmp::encode::write_array_len(buf, items.len() as u32)?;
+                for (i, item) in items.iter().enumerate() {
+                    content.encode_into(&format!("{path}[{i}]"), types, item, buf)?;
+                }
+            }
+            Type::Tuple { content, .. } => {
+                let items = value.as_array().ok_or_else(|| mismatch(path, "expected array"))?;
+                let indexed = content
+                    .iter()
+                    .enumerate()
+                    .map(|(i, sc)| (i.to_string(), sc, items.get(i)));
+                encode_indexed(path, types, indexed, buf)?;
+            }
+            Type::Struct { content, .. } => {
+                let obj = value.as_object().ok_or_else(|| mismatch(path, "expected object"))?;
+                let indexed = content
+                    .iter()
+                    .map(|(name, sc)| (name.clone(), sc, obj.get(name)));
+                encode_indexed(path, types, indexed, buf)?;
+            }
+            Type::Enum { content, .. } => {
+                let obj = value
+                    .as_object()
+                    .ok_or_else(|| mismatch(path, "expected single-key object"))?;
+                let (variant, inner) = obj
+                    .iter()
+                    .next()
+                    .ok_or_else(|| mismatch(path, "enum object has no variant"))?;
+                let sc = content
+                    .get(variant)
+                    .ok_or_else(|| mismatch(path, "unknown enum variant"))?;
+                rmp::encode::write_array_len(buf, 2)?;
+                rmp::encode::write_uint(buf, sc.index as u64)?;
+                sc.content
+                    .encode_into(&format!("{path}.{variant}"), types, inner, buf)?;
+            }
+            Type::NamedType { .. } => unreachable!("resolve() strips NamedType"),
+        }
+        Ok(())
+    }
+
+    fn decode_from(
+        &self,
+        path: &str,
+        types: &IndexMap<String, Type>,
+        cur: &mut Cursor<&[u8]>,
+    ) -> Result<serde_json::Value, CodecError> {
+        let ty = self.resolve(path, types)?;
+        Ok(match ty {
+            Type::Null { .. } => {
+                rmp::decode::read_nil(cur)?;
+                serde_json::Value::Null
+            }
+            Type::Bool { .. } => serde_json::Value::Bool(rmp::decode::read_bool(cur)?),
+            Type::I32 { .. } | Type::I64 { .. } => {
+                serde_json::Value::from(rmp::decode::read_int::<i64, _>(cur)?)
+            }
+            Type::U32 { .. } | Type::U64 { .. } => {
+                serde_json::Value::from(rmp::decode::read_int::<u64, _>(cur)?)
+            }
+            Type::F64 { .. } => serde_json::Value::from(rmp::decode::read_f64(cur)?),
+            Type::String { .. } => {
+                let len = rmp::decode::read_str_len(cur)? as usize;
+                let mut out = vec![0u8; len];
+                cur.read_exact(&mut out)
+                    .map_err(|e| CodecError::Decode(e.to_string()))?;
+                serde_json::Value::String(
+                    String::from_utf8(out).map_err(|e| CodecError::Decode(e.to_string()))?,
+                )
+            }
+            Type::Bytes { .. } => {
+                let len = rmp::decode::read_bin_len(cur)? as usize;
+                let mut out = vec![0u8; len];
+                cur.read_exact(&mut out)
+                    .map_err(|e| CodecError::Decode(e.to_string()))?;
+                serde_json::Value::String(base64_encode(&out))
+            }
+            Type::Optional { content, .. } => {
+                if peek_is_nil(cur) {
+                    rmp::decode::read_nil(cur)?;
+                    serde_json::Value::Null
+                } else {
+                    content.decode_from(path, types, cur)?
+                }
+            }
+            Type::Array { content, .. } => {
+                let len = rmp::decode::read_array_len(cur)?;
+                let mut out = Vec::with_capacity(len as usize);
+                for i in 0..len {
+                    out.push(content.decode_from(&format!("{path}[{i}]"), types, cur)?);
+                }
+                serde_json::Value::Array(out)
+            }
+            Type::Tuple { content, .. } => {
+                serde_json::Value::Array(decode_indexed(path, types, content.iter(), cur)?)
+            }
+            Type::Struct { content, .. } => {
+                let values = decode_indexed(path, types, content.values(), cur)?;
+                let mut out = serde_json::Map::new();
+                for ((name, _), value) in content.iter().zip(values) {
+                    out.insert(name.clone(), value);
+                }
+                serde_json::Value::Object(out)
+            }
+            Type::Enum { content, .. } => {
+                let len = rmp::decode::read_array_len(cur)?;
+                if len != 2 {
+                    return Err(mismatch(path, "enum wire value must be a length-2 array"));
+                }
+                let index = rmp::decode::read_int::<u32, _>(cur)?;
+                let (variant, sc) = content
+                    .iter()
+                    .find(|(_, sc)| sc.index == index)
+                    .ok_or_else(|| mismatch(path, "unknown enum index"))?;
+                let inner = sc
+                    .content
+                    .decode_from(&format!("{path}.{variant}"), types, cur)?;
+                let mut out = serde_json::Map::new();
+                out.insert(variant.clone(), inner);
+                serde_json::Value::Object(out)
+            }
+            Type::NamedType { .. } => unreachable!("resolve() strips NamedType"),
+        })
+    }
+}
+
+/// Shared encode logic for `Struct`/`Tuple`: members are written into a
+/// msgpack array sized to `max(index) + 1`, with gap positions written as
+/// nil.
+fn encode_indexed<'a>(
+    path: &str,
+    types: &IndexMap<String, Type>,
+    items: impl Iterator<Item = (String, &'a StructContent, Option<&'a serde_json::Value>)> + Clone,
+    buf: &mut Vec<u8>,
+) -> Result<(), CodecError> {
+    let size = items.clone().map(|(_, sc, _)| sc.index + 1).max().unwrap_or(0);
+    let mut slots: Vec<Option<(String, &StructContent, &serde_json::Value)>> =
+        vec![None; size as usize];
+    for (member, sc, value) in items {
+        let member_path = format!("{path}.{member}");
+        match value {
+            Some(value) => slots[sc.index as usize] = Some((member_path, sc, value)),
+            None if matches!(*sc.content, Type::Optional { .. }) => {}
+            None => return Err(CodecError::MissingField { path: member_path }),
+        }
+    }
+    rmp::encode::write_array_len(buf, size)?;
+    for slot in slots {
+        match slot {
+            Some((member_path, sc, value)) => {
+                sc.content.encode_into(&member_path, types, value, buf)?
+            }
+            None => rmp::encode::write_nil(buf)?,
+        }
+    }
+    Ok(())
+}
+
+/// Shared decode logic for `Struct`/`Tuple`: reads the msgpack array and
+/// plucks each member's value out of its `index` slot, in wire order. A
+/// member whose `index` lies at or past the end of the array (such as an
+/// omitted trailing optional) decodes as `null`.
+fn decode_indexed<'a>(
+    path: &str,
+    types: &IndexMap<String, Type>,
+    items: impl Iterator<Item = &'a StructContent> + Clone,
+    cur: &mut Cursor<&[u8]>,
+) -> Result<Vec<serde_json::Value>, CodecError> {
+    let len = rmp::decode::read_array_len(cur)?;
+
+    let mut by_index: HashMap<u32, &StructContent> = HashMap::new();
+    for sc in items.clone() {
+        by_index.insert(sc.index, sc);
+    }
+
+    let mut decoded: HashMap<u32, serde_json::Value> = HashMap::new();
+    for i in 0..len {
+        match by_index.get(&i) {
+            Some(sc) => {
+                let value = sc.content.decode_from(&format!("{path}[{i}]"), types, cur)?;
+                decoded.insert(i, value);
+            }
+            None => {
+                rmp::decode::read_nil(cur)?;
+            }
+        }
+    }
+
+    Ok(items
+        .map(|sc| decoded.remove(&sc.index).unwrap_or(serde_json::Value::Null))
+        .collect())
+}
+
+fn base64_encode(bytes: &[u8]) -> String {
+    base64::engine::general_purpose::STANDARD.encode(bytes)
+}
+
+fn base64_decode(s: &str) -> Option<Vec<u8>> {
+    base64::engine::general_purpose::STANDARD.decode(s).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn types_with(name: &str, ty: Type) -> IndexMap<String, Type> {
+        let mut types = IndexMap::new();
+        types.insert(name.to_string(), ty);
+        types
+    }
+
+    #[test]
+    fn round_trip_primitives() {
+        let types = IndexMap::new();
+        let ty = Type::I32 { doc: None };
+        let value = serde_json::json!(-42);
+        let bytes = ty.encode_value(&types, &value).unwrap();
+        assert_eq!(value, ty.decode_value(&types, &bytes).unwrap());
+    }
+
+    #[test]
+    fn round_trip_struct_with_gap_index() {
+        let mut content = IndexMap::new();
+        content.insert(
+            "name".to_string(),
+            StructContent {
+                index: 0,
+                content: Box::new(Type::String { doc: None }),
+            },
+        );
+        content.insert(
+            "age".to_string(),
+            StructContent {
+                index: 2,
+                content: Box::new(Type::U32 { doc: None }),
+            },
+        );
+        let types = types_with("Person", Type::Struct { doc: None, content });
+        let ty = Type::NamedType {
+            doc: None,
+            content: "Person".to_string(),
+        };
+        let value = serde_json::json!({ "name": "Ada", "age": 36 });
+        let bytes = ty.encode_value(&types, &value).unwrap();
+        // index 1 is a gap and must be encoded as msgpack nil.
+        assert_eq!(3, rmp::decode::read_array_len(&mut Cursor::new(&bytes[..])).unwrap());
+        assert_eq!(value, ty.decode_value(&types, &bytes).unwrap());
+    }
+
+    #[test]
+    fn round_trip_enum() {
+        let mut content = IndexMap::new();
+        content.insert(
+            "Ok".to_string(),
+            StructContent {
+                index: 0,
+                content: Box::new(Type::String { doc: None }),
+            },
+        );
+        content.insert(
+            "Err".to_string(),
+            StructContent {
+                index: 1,
+                content: Box::new(Type::String { doc: None }),
+            },
+        );
+        let types = IndexMap::new();
+        let ty = Type::Enum { doc: None, content };
+        let value = serde_json::json!({ "Err": "boom" });
+        let bytes = ty.encode_value(&types, &value).unwrap();
+        assert_eq!(value, ty.decode_value(&types, &bytes).unwrap());
+    }
+
+    #[test]
+    fn missing_trailing_optional_omitted_on_decode() {
+        let mut content = IndexMap::new();
+        content.insert(
+            "name".to_string(),
+            StructContent {
+                index: 0,
+                content: Box::new(Type::String { doc: None }),
+            },
+        );
+        content.insert(
+            "nickname".to_string(),
+            StructContent {
+                index: 1,
+                content: Box::new(Type::Optional {
+                    doc: None,
+                    content: Box::new(Type::String { doc: None }),
+                }),
+            },
+        );
+        let types = IndexMap::new();
+        let ty = Type::Struct { doc: None, content };
+
+        let mut buf = Vec::new();
+        rmp::encode::write_array_len(&mut buf, 1).unwrap();
+        rmp::encode::write_str(&mut buf, "Ada").unwrap();
+
+        let decoded = ty.decode_value(&types, &buf).unwrap();
+        assert_eq!(
+            serde_json::json!({ "name": "Ada", "nickname": null }),
+            decoded
+        );
+    }
+}
@@ -0,0 +1,700 @@
+//! Revision-aware backward-compatibility diffing between two [`IApiSpec`]s.
+
+use crate::*;
+
+/// Whether a [`Change`] is safe for an implementation to ignore, or requires
+/// it to be updated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeCategory {
+    /// An existing implementation remains valid against the newer spec.
+    NonBreaking,
+    /// An existing implementation may no longer be valid against the newer
+    /// spec.
+    Breaking,
+}
+
+/// A single difference found between two specs by [`IApiSpec::diff`].
+#[derive(Debug, Clone, PartialEq, Eq, Display)]
+pub enum Change {
+    /// A `Feature` or `UnstableFeature` was added.
+    #[display(fmt = "feature `{name}` added")]
+    FeatureAdded {
+        /// name of the new feature
+        name: String,
+    },
+
+    /// A stable `Feature` was removed outright.
+    #[display(fmt = "stable feature `{name}` removed")]
+    FeatureRemoved {
+        /// name of the removed feature
+        name: String,
+    },
+
+    /// A stable `Feature` was demoted to an `UnstableFeature`.
+    #[display(fmt = "stable feature `{name}` demoted to unstable")]
+    FeatureDemoted {
+        /// name of the demoted feature
+        name: String,
+    },
+
+    /// A stable `Feature` was marked `deprecated`.
+    #[display(fmt = "feature `{name}` marked deprecated")]
+    FeatureDeprecated {
+        /// name of the deprecated feature
+        name: String,
+    },
+
+    /// A new entry was added to `types`.
+    #[display(fmt = "type `{name}` added")]
+    TypeAdded {
+        /// name of the new type
+        name: String,
+    },
+
+    /// A named scalar type was narrowed (e.g. `I64` to `I32`), which could
+    /// truncate values an existing implementation relies on.
+    #[display(fmt = "`{path}` narrowed from {old} to {new}")]
+    ScalarNarrowed {
+        /// dotted path to the narrowed type
+        path: String,
+        /// the previous scalar kind
+        old: String,
+        /// the new, narrower scalar kind
+        new: String,
+    },
+
+    /// A `Struct`/`Tuple`/`Enum` member's `StructContent.index` changed,
+    /// which changes its wire position.
+    #[display(fmt = "`{path}` index changed from {old} to {new}")]
+    MemberIndexChanged {
+        /// dotted path to the member whose index changed
+        path: String,
+        /// the previous wire index
+        old: u32,
+        /// the new wire index
+        new: u32,
+    },
+
+    /// A `Struct`/`Enum`/`Tuple` gained a new, `Optional` member at any
+    /// index, which existing payloads simply omit.
+    #[display(fmt = "`{path}` gained optional member")]
+    OptionalMemberAdded {
+        /// dotted path to the new member
+        path: String,
+    },
+
+    /// A `Struct`/`Enum`/`Tuple` gained a new, non-`Optional` member, which
+    /// existing payloads never supply.
+    #[display(fmt = "`{path}` gained required member")]
+    RequiredMemberAdded {
+        /// dotted path to the new member
+        path: String,
+    },
+
+    /// A member was removed from a `Struct`/`Enum`/`Tuple`.
+    #[display(fmt = "`{path}` member removed")]
+    MemberRemoved {
+        /// dotted path to the removed member
+        path: String,
+    },
+
+    /// A named type's shape changed in a way not covered by a more specific
+    /// rule: its variant changed (e.g. `Enum` to `Struct`), or a
+    /// `NamedType` was retargeted to a different name.
+    #[display(fmt = "`{path}` changed from {old} to {new}")]
+    TypeKindChanged {
+        /// dotted path to the changed type
+        path: String,
+        /// the previous shape
+        old: String,
+        /// the new shape
+        new: String,
+    },
+
+    /// A new `Call` was added.
+    #[display(fmt = "call `{path}` added")]
+    CallAdded {
+        /// dotted path to the new call
+        path: String,
+    },
+
+    /// A `Call` was removed.
+    #[display(fmt = "call `{path}` removed")]
+    CallRemoved {
+        /// dotted path to the removed call
+        path: String,
+    },
+
+    /// A `Call`'s `input`, `output`, or the spec's `error_type` named a
+    /// different type.
+    #[display(fmt = "`{path}` changed from `{old}` to `{new}`")]
+    CallTypeChanged {
+        /// dotted path to the changed field
+        path: String,
+        /// the previous type name
+        old: String,
+        /// the new type name
+        new: String,
+    },
+}
+
+impl Change {
+    /// Whether this change could break an existing implementation.
+    pub fn category(&self) -> ChangeCategory {
+        use Change::*;
+        match self {
+            FeatureAdded { .. }
+            | FeatureDeprecated { .. }
+            | TypeAdded { .. }
+            | OptionalMemberAdded { .. }
+            | CallAdded { .. } => ChangeCategory::NonBreaking,
+            FeatureRemoved { .. }
+            | FeatureDemoted { .. }
+            | ScalarNarrowed { .. }
+            | MemberIndexChanged { .. }
+            | RequiredMemberAdded { .. }
+            | MemberRemoved { .. }
+            | TypeKindChanged { .. }
+            | CallRemoved { .. }
+            | CallTypeChanged { .. } => ChangeCategory::Breaking,
+        }
+    }
+}
+
+/// The result of [`IApiSpec::diff`]: every change found between two specs,
+/// classified as breaking or non-breaking.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpecDiff {
+    /// revision of the older spec
+    pub old_revision: u32,
+    /// revision of the newer spec
+    pub new_revision: u32,
+    /// every change found, in no particular order
+    pub changes: Vec<Change>,
+}
+
+impl SpecDiff {
+    /// True only when the newer spec's revision is greater than the older
+    /// spec's, and no breaking changes were found.
+    pub fn is_compatible(&self) -> bool {
+        self.new_revision > self.old_revision
+            && !self
+                .changes
+                .iter()
+                .any(|c| c.category() == ChangeCategory::Breaking)
+    }
+}
+
+fn scalar_kind(ty: &Type) -> Option<&'static str> {
+    match ty {
+        Type::I32 { .. } => Some("I32"),
+        Type::U32 { .. } => Some("U32"),
+        Type::I64 { .. } => Some("I64"),
+        Type::U64 { .. } => Some("U64"),
+        _ => None,
+    }
+}
+
+fn scalar_width(kind: &str) -> u8 {
+    match kind {
+        "I32" | "U32" => 32,
+        "I64" | "U64" => 64,
+        _ => 0,
+    }
+}
+
+fn scalar_signed(kind: &str) -> bool {
+    matches!(kind, "I32" | "I64")
+}
+
+/// Whether going from `old_kind` to `new_kind` narrows the representable
+/// range: either the bit width shrinks, or the width stays the same but
+/// signedness flips (e.g. `U32` -> `I32` roughly halves the positive
+/// range).
+fn scalar_narrows(old_kind: &str, new_kind: &str) -> bool {
+    let (old_width, new_width) = (scalar_width(old_kind), scalar_width(new_kind));
+    new_width < old_width || (new_width == old_width && scalar_signed(new_kind) != scalar_signed(old_kind))
+}
+
+impl IApiSpec {
+    /// Diff this spec against a `newer` revision of itself, classifying
+    /// every change as breaking or non-breaking so a broker can decide
+    /// whether to accept the newer spec.
+    pub fn diff(&self, newer: &IApiSpec) -> SpecDiff {
+        let mut changes = Vec::new();
+
+        self.diff_features(newer, &mut changes);
+        self.diff_types(newer, &mut changes);
+        self.diff_calls("calls_in", &self.calls_in, &newer.calls_in, &mut changes);
+        self.diff_calls("calls_out", &self.calls_out, &newer.calls_out, &mut changes);
+
+        if self.error_type != newer.error_type {
+            changes.push(Change::CallTypeChanged {
+                path: "error_type".to_string(),
+                old: self.error_type.clone(),
+                new: newer.error_type.clone(),
+            });
+        }
+
+        SpecDiff {
+            old_revision: self.revision,
+            new_revision: newer.revision,
+            changes,
+        }
+    }
+
+    fn diff_features(&self, newer: &IApiSpec, changes: &mut Vec<Change>) {
+        for name in newer.features.keys() {
+            if !self.features.contains_key(name) {
+                changes.push(Change::FeatureAdded { name: name.clone() });
+            }
+        }
+        for name in newer.unstable_features.keys() {
+            if !self.unstable_features.contains_key(name) && !self.features.contains_key(name) {
+                changes.push(Change::FeatureAdded { name: name.clone() });
+            }
+        }
+        for (name, old_feature) in &self.features {
+            match newer.features.get(name) {
+                Some(new_feature) => {
+                    if old_feature.deprecated != Some(true) && new_feature.deprecated == Some(true)
+                    {
+                        changes.push(Change::FeatureDeprecated { name: name.clone() });
+                    }
+                }
+                None if newer.unstable_features.contains_key(name) => {
+                    changes.push(Change::FeatureDemoted { name: name.clone() });
+                }
+                None => {
+                    changes.push(Change::FeatureRemoved { name: name.clone() });
+                }
+            }
+        }
+    }
+
+    fn diff_types(&self, newer: &IApiSpec, changes: &mut Vec<Change>) {
+        for name in newer.types.keys() {
+            if !self.types.contains_key(name) {
+                changes.push(Change::TypeAdded { name: name.clone() });
+            }
+        }
+        for (name, old_ty) in &self.types {
+            let Some(new_ty) = newer.types.get(name) else {
+                continue;
+            };
+            let path = format!("types.{name}");
+            diff_type(&path, old_ty, new_ty, changes);
+        }
+    }
+
+    fn diff_calls(
+        &self,
+        group: &str,
+        old_calls: &IndexMap<String, Call>,
+        new_calls: &IndexMap<String, Call>,
+        changes: &mut Vec<Change>,
+    ) {
+        for name in new_calls.keys() {
+            if !old_calls.contains_key(name) {
+                changes.push(Change::CallAdded {
+                    path: format!("{group}.{name}"),
+                });
+            }
+        }
+        for (name, old_call) in old_calls {
+            let Some(new_call) = new_calls.get(name) else {
+                changes.push(Change::CallRemoved {
+                    path: format!("{group}.{name}"),
+                });
+                continue;
+            };
+            let path = format!("{group}.{name}");
+            if old_call.input != new_call.input {
+                changes.push(Change::CallTypeChanged {
+                    path: format!("{path}.input"),
+                    old: old_call.input.clone(),
+                    new: new_call.input.clone(),
+                });
+            }
+            if old_call.output != new_call.output {
+                changes.push(Change::CallTypeChanged {
+                    path: format!("{path}.output"),
+                    old: old_call.output.clone(),
+                    new: new_call.output.clone(),
+                });
+            }
+        }
+    }
+}
+
+fn diff_type(path: &str, old_ty: &Type, new_ty: &Type, changes: &mut Vec<Change>) {
+    if let (Some(old_kind), Some(new_kind)) = (scalar_kind(old_ty), scalar_kind(new_ty)) {
+        if old_kind != new_kind && scalar_narrows(old_kind, new_kind) {
+            changes.push(Change::ScalarNarrowed {
+                path: path.to_string(),
+                old: old_kind.to_string(),
+                new: new_kind.to_string(),
+            });
+        }
+        return;
+    }
+
+    match (old_ty, new_ty) {
+        (Type::Struct { content: old_c, .. }, Type::Struct { content: new_c, .. })
+        | (Type::Enum { content: old_c, .. }, Type::Enum { content: new_c, .. }) => {
+            let old_members = old_c.iter().map(|(k, sc)| (k.clone(), sc));
+            let new_members = new_c.iter().map(|(k, sc)| (k.clone(), sc));
+            diff_members(path, old_members, |k| old_c.get(k), new_members, changes);
+        }
+        (Type::Tuple { content: old_c, .. }, Type::Tuple { content: new_c, .. }) => {
+            let old_members = old_c.iter().enumerate().map(|(i, sc)| (i.to_string(), sc));
+            let new_members = new_c.iter().enumerate().map(|(i, sc)| (i.to_string(), sc));
+            let by_position = |k: &str| k.parse::<usize>().ok().and_then(|i| old_c.get(i));
+            diff_members(path, old_members, by_position, new_members, changes);
+        }
+        (Type::Optional { content: old_c, .. }, Type::Optional { content: new_c, .. })
+        | (Type::Array { content: old_c, .. }, Type::Array { content: new_c, .. }) => {
+            diff_type(&format!("{path}.content"), old_c, new_c, changes);
+        }
+        _ => {
+            let (old_sig, new_sig) = (type_signature(old_ty), type_signature(new_ty));
+            if old_sig != new_sig {
+                changes.push(Change::TypeKindChanged {
+                    path: path.to_string(),
+                    old: old_sig,
+                    new: new_sig,
+                });
+            }
+        }
+    }
+}
+
+/// A string identifying the wire-relevant shape of a `Type`: its variant
+/// name, plus the referenced name for `NamedType` since retargeting an
+/// alias changes what it resolves to even though the variant is unchanged.
+fn type_signature(ty: &Type) -> String {
+    match ty {
+        Type::NamedType { content, .. } => format!("NamedType({content})"),
+        Type::Null { .. } => "Null".to_string(),
+        Type::Bool { .. } => "Bool".to_string(),
+        Type::I32 { .. } => "I32".to_string(),
+        Type::U32 { .. } => "U32".to_string(),
+        Type::I64 { .. } => "I64".to_string(),
+        Type::U64 { .. } => "U64".to_string(),
+        Type::F64 { .. } => "F64".to_string(),
+        Type::Bytes { .. } => "Bytes".to_string(),
+        Type::String { .. } => "String".to_string(),
+        Type::Optional { .. } => "Optional".to_string(),
+        Type::Array { .. } => "Array".to_string(),
+        Type::Tuple { .. } => "Tuple".to_string(),
+        Type::Struct { .. } => "Struct".to_string(),
+        Type::Enum { .. } => "Enum".to_string(),
+    }
+}
+
+/// Shared member diffing for `Struct`/`Enum`/`Tuple`: reports members added
+/// (optional vs required), removed, reindexed, or recursively changed.
+fn diff_members<'a>(
+    path: &str,
+    old_members: impl Iterator<Item = (String, &'a StructContent)>,
+    lookup_old: impl Fn(&str) -> Option<&'a StructContent>,
+    new_members: impl Iterator<Item = (String, &'a StructContent)> + Clone,
+    changes: &mut Vec<Change>,
+) {
+    let new_keys: std::collections::HashSet<String> =
+        new_members.clone().map(|(k, _)| k).collect();
+    for (member, new_sc) in new_members {
+        let member_path = format!("{path}.{member}");
+        match lookup_old(&member) {
+            Some(old_sc) => {
+                if old_sc.index != new_sc.index {
+                    changes.push(Change::MemberIndexChanged {
+                        path: member_path.clone(),
+                        old: old_sc.index,
+                        new: new_sc.index,
+                    });
+                }
+                diff_type(&member_path, &old_sc.content, &new_sc.content, changes);
+            }
+            None => {
+                if matches!(*new_sc.content, Type::Optional { .. }) {
+                    changes.push(Change::OptionalMemberAdded { path: member_path });
+                } else {
+                    changes.push(Change::RequiredMemberAdded { path: member_path });
+                }
+            }
+        }
+    }
+    for (member, _) in old_members {
+        if !new_keys.contains(&member) {
+            changes.push(Change::MemberRemoved {
+                path: format!("{path}.{member}"),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn base_spec() -> IApiSpec {
+        let mut types = IndexMap::new();
+        types.insert("Unit".to_string(), Type::Null { doc: None });
+        IApiSpec {
+            id: NanoId::default(),
+            title: "t".to_string(),
+            revision: 1,
+            error_type: "Unit".to_string(),
+            unique: None,
+            features: IndexMap::new(),
+            unstable_features: IndexMap::new(),
+            types,
+            calls_out: IndexMap::new(),
+            calls_in: IndexMap::new(),
+        }
+    }
+
+    #[test]
+    fn bumped_revision_with_no_changes_is_compatible() {
+        let old = base_spec();
+        let mut newer = base_spec();
+        newer.revision = 2;
+        assert!(old.diff(&newer).is_compatible());
+    }
+
+    #[test]
+    fn same_revision_is_not_compatible() {
+        let old = base_spec();
+        let newer = base_spec();
+        assert!(!old.diff(&newer).is_compatible());
+    }
+
+    #[test]
+    fn narrowed_scalar_is_breaking() {
+        let mut old = base_spec();
+        old.revision = 1;
+        old.types.insert("Count".to_string(), Type::I64 { doc: None });
+        let mut newer = old.clone();
+        newer.revision = 2;
+        newer.types.insert("Count".to_string(), Type::I32 { doc: None });
+
+        let diff = old.diff(&newer);
+        assert!(!diff.is_compatible());
+        assert!(diff.changes.contains(&Change::ScalarNarrowed {
+            path: "types.Count".to_string(),
+            old: "I64".to_string(),
+            new: "I32".to_string(),
+        }));
+    }
+
+    #[test]
+    fn same_width_sign_change_is_breaking() {
+        let mut old = base_spec();
+        old.types.insert("Count".to_string(), Type::U32 { doc: None });
+        let mut newer = old.clone();
+        newer.revision = 2;
+        newer.types.insert("Count".to_string(), Type::I32 { doc: None });
+
+        let diff = old.diff(&newer);
+        assert!(!diff.is_compatible());
+        assert!(diff.changes.contains(&Change::ScalarNarrowed {
+            path: "types.Count".to_string(),
+            old: "U32".to_string(),
+            new: "I32".to_string(),
+        }));
+    }
+
+    #[test]
+    fn removed_stable_feature_is_breaking() {
+        let mut old = base_spec();
+        old.features.insert(
+            "core".to_string(),
+            Feature {
+                doc: None,
+                stablized_revision: 1,
+                deprecated: None,
+            },
+        );
+        let mut newer = old.clone();
+        newer.revision = 2;
+        newer.features.shift_remove("core");
+
+        let diff = old.diff(&newer);
+        assert!(!diff.is_compatible());
+        assert!(diff
+            .changes
+            .contains(&Change::FeatureRemoved { name: "core".to_string() }));
+    }
+
+    #[test]
+    fn required_member_added_to_struct_is_breaking() {
+        let mut old = base_spec();
+        let mut content = IndexMap::new();
+        content.insert(
+            "name".to_string(),
+            StructContent {
+                index: 0,
+                content: Box::new(Type::String { doc: None }),
+            },
+        );
+        old.types
+            .insert("Person".to_string(), Type::Struct { doc: None, content });
+
+        let mut newer = old.clone();
+        newer.revision = 2;
+        if let Some(Type::Struct { content, .. }) = newer.types.get_mut("Person") {
+            content.insert(
+                "age".to_string(),
+                StructContent {
+                    index: 1,
+                    content: Box::new(Type::U32 { doc: None }),
+                },
+            );
+        }
+
+        let diff = old.diff(&newer);
+        assert!(!diff.is_compatible());
+        assert!(diff.changes.contains(&Change::RequiredMemberAdded {
+            path: "types.Person.age".to_string(),
+        }));
+    }
+
+    #[test]
+    fn removed_struct_member_is_breaking() {
+        let mut old = base_spec();
+        let mut content = IndexMap::new();
+        content.insert(
+            "name".to_string(),
+            StructContent {
+                index: 0,
+                content: Box::new(Type::String { doc: None }),
+            },
+        );
+        old.types
+            .insert("Person".to_string(), Type::Struct { doc: None, content });
+
+        let mut newer = old.clone();
+        newer.revision = 2;
+        if let Some(Type::Struct { content, .. }) = newer.types.get_mut("Person") {
+            content.shift_remove("name");
+        }
+
+        let diff = old.diff(&newer);
+        assert!(!diff.is_compatible());
+        assert!(diff.changes.contains(&Change::MemberRemoved {
+            path: "types.Person.name".to_string(),
+        }));
+    }
+
+    #[test]
+    fn reindexed_tuple_member_is_breaking() {
+        let mut old = base_spec();
+        old.types.insert(
+            "Pair".to_string(),
+            Type::Tuple {
+                doc: None,
+                content: vec![StructContent {
+                    index: 0,
+                    content: Box::new(Type::I64 { doc: None }),
+                }],
+            },
+        );
+
+        let mut newer = old.clone();
+        newer.revision = 2;
+        newer.types.insert(
+            "Pair".to_string(),
+            Type::Tuple {
+                doc: None,
+                content: vec![StructContent {
+                    index: 1,
+                    content: Box::new(Type::I64 { doc: None }),
+                }],
+            },
+        );
+
+        let diff = old.diff(&newer);
+        assert!(!diff.is_compatible());
+        assert!(diff.changes.contains(&Change::MemberIndexChanged {
+            path: "types.Pair.0".to_string(),
+            old: 0,
+            new: 1,
+        }));
+    }
+
+    #[test]
+    fn type_kind_change_is_breaking() {
+        let mut old = base_spec();
+        old.types.insert("Widget".to_string(), Type::String { doc: None });
+        let mut newer = old.clone();
+        newer.revision = 2;
+        let mut content = IndexMap::new();
+        content.insert(
+            "name".to_string(),
+            StructContent {
+                index: 0,
+                content: Box::new(Type::String { doc: None }),
+            },
+        );
+        newer
+            .types
+            .insert("Widget".to_string(), Type::Struct { doc: None, content });
+
+        let diff = old.diff(&newer);
+        assert!(!diff.is_compatible());
+        assert!(diff.changes.contains(&Change::TypeKindChanged {
+            path: "types.Widget".to_string(),
+            old: "String".to_string(),
+            new: "Struct".to_string(),
+        }));
+    }
+
+    #[test]
+    fn retargeted_named_type_alias_is_breaking() {
+        let mut old = base_spec();
+        old.types.insert("A".to_string(), Type::Null { doc: None });
+        old.types.insert("B".to_string(), Type::Null { doc: None });
+        old.types.insert(
+            "Alias".to_string(),
+            Type::NamedType { doc: None, content: "A".to_string() },
+        );
+        let mut newer = old.clone();
+        newer.revision = 2;
+        newer.types.insert(
+            "Alias".to_string(),
+            Type::NamedType { doc: None, content: "B".to_string() },
+        );
+
+        let diff = old.diff(&newer);
+        assert!(!diff.is_compatible());
+        assert!(diff.changes.contains(&Change::TypeKindChanged {
+            path: "types.Alias".to_string(),
+            old: "NamedType(A)".to_string(),
+            new: "NamedType(B)".to_string(),
+        }));
+    }
+
+    #[test]
+    fn removed_call_is_breaking() {
+        let mut old = base_spec();
+        old.calls_in.insert(
+            "ping".to_string(),
+            Call {
+                doc: None,
+                feature: "core".to_string(),
+                input: "Unit".to_string(),
+                output: "Unit".to_string(),
+            },
+        );
+        let mut newer = old.clone();
+        newer.revision = 2;
+        newer.calls_in.shift_remove("ping");
+
+        let diff = old.diff(&newer);
+        assert!(!diff.is_compatible());
+        assert!(diff.changes.contains(&Change::CallRemoved {
+            path: "calls_in.ping".to_string(),
+        }));
+    }
+}
@@ -0,0 +1,65 @@
+//! Format-agnostic parsing of an [`IApiSpecDoc`] via pluggable coders.
+
+use crate::*;
+
+/// The wire format a spec document is encoded in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// `application/json`
+    Json,
+    /// `application/yaml`
+    Yaml,
+}
+
+impl Format {
+    /// Sniff the format of `data` from its leading bytes: a spec starting
+    /// with `{` (ignoring leading whitespace) is assumed to be JSON,
+    /// anything else is assumed to be YAML.
+    pub fn sniff(data: &[u8]) -> Self {
+        match data.iter().find(|b| !b.is_ascii_whitespace()) {
+            Some(b'{') => Self::Json,
+            _ => Self::Yaml,
+        }
+    }
+
+    fn decode<T: serde::de::DeserializeOwned>(self, data: &[u8]) -> std::io::Result<T> {
+        match self {
+            Self::Json => serde_json::from_slice(data)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e)),
+            Self::Yaml => serde_yaml::from_slice(data)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e)),
+        }
+    }
+}
+
+impl IApiSpecDoc {
+    /// Parse spec document data encoded in the given `format`.
+    pub fn parse_with(data: &[u8], format: Format) -> std::io::Result<Self> {
+        format.decode(data)
+    }
+
+    /// Parse spec document data, sniffing its format from the leading
+    /// bytes. See [`Format::sniff`].
+    pub fn parse_auto(data: &[u8]) -> std::io::Result<Self> {
+        Self::parse_with(data, Format::sniff(data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sniffs_json_and_yaml() {
+        assert_eq!(Format::Json, Format::sniff(b"  {\"a\": 1}"));
+        assert_eq!(Format::Yaml, Format::sniff(b"a: 1\n"));
+    }
+
+    #[test]
+    fn yaml_round_trips_through_json() {
+        let json_doc = IApiSpecDoc::parse(include_bytes!("fixture_spec.json")).unwrap();
+        let yaml = serde_yaml::to_string(&json_doc).unwrap();
+        let yaml_doc = IApiSpecDoc::parse_auto(yaml.as_bytes()).unwrap();
+        assert_eq!(json_doc, yaml_doc);
+    }
+}
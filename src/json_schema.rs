@@ -0,0 +1,148 @@
+//! Deriving a JSON Schema from an [`IApiSpec`], so consumers can validate
+//! concrete call payloads without reimplementing the type system.
+
+use crate::*;
+use serde_json::{json, Value};
+
+impl IApiSpec {
+    /// Translate every entry in `types` into a JSON Schema definition under
+    /// `$defs`, with `NamedType` becoming a `$ref` to `#/$defs/<name>`.
+    pub fn to_json_schema(&self) -> Value {
+        let mut defs = serde_json::Map::new();
+        for (name, ty) in &self.types {
+            defs.insert(name.clone(), type_to_schema(ty));
+        }
+        json!({
+            "$schema": "http://json-schema.org/draft-07/schema#",
+            "$defs": defs,
+        })
+    }
+}
+
+fn type_to_schema(ty: &Type) -> Value {
+    let mut schema = match ty {
+        Type::Null { .. } => json!({ "type": "null" }),
+        Type::Bool { .. } => json!({ "type": "boolean" }),
+        Type::I32 { .. } => json!({
+            "type": "integer",
+            "minimum": i32::MIN,
+            "maximum": i32::MAX,
+        }),
+        Type::U32 { .. } => json!({
+            "type": "integer",
+            "minimum": u32::MIN,
+            "maximum": u32::MAX,
+        }),
+        Type::I64 { .. } => json!({
+            "type": "integer",
+            "minimum": i64::MIN,
+            "maximum": i64::MAX,
+        }),
+        Type::U64 { .. } => json!({
+            "type": "integer",
+            "minimum": u64::MIN,
+            "maximum": u64::MAX,
+        }),
+        Type::F64 { .. } => json!({ "type": "number" }),
+        Type::Bytes { .. } => json!({
+            "type": "string",
+            "contentEncoding": "base64",
+        }),
+        Type::String { .. } => json!({ "type": "string" }),
+        Type::Optional { content, .. } => {
+            json!({ "anyOf": [type_to_schema(content), { "type": "null" }] })
+        }
+        Type::Array { content, .. } => json!({
+            "type": "array",
+            "items": type_to_schema(content),
+        }),
+        Type::Tuple { content, .. } => {
+            let mut ordered: Vec<&StructContent> = content.iter().collect();
+            ordered.sort_by_key(|sc| sc.index);
+            json!({
+                "type": "array",
+                "items": ordered.into_iter().map(|sc| type_to_schema(&sc.content)).collect::<Vec<_>>(),
+            })
+        }
+        Type::Struct { content, .. } => {
+            let mut properties = serde_json::Map::new();
+            let mut required = Vec::new();
+            for (name, sc) in content {
+                if !matches!(*sc.content, Type::Optional { .. }) {
+                    required.push(json!(name));
+                }
+                properties.insert(name.clone(), type_to_schema(&sc.content));
+            }
+            json!({
+                "type": "object",
+                "properties": properties,
+                "required": required,
+            })
+        }
+        Type::Enum { content, .. } => {
+            let one_of: Vec<Value> = content
+                .iter()
+                .map(|(name, sc)| {
+                    json!({
+                        "type": "object",
+                        "properties": { name: type_to_schema(&sc.content) },
+                        "required": [name],
+                        "additionalProperties": false,
+                    })
+                })
+                .collect();
+            json!({ "oneOf": one_of })
+        }
+        Type::NamedType { content, .. } => json!({ "$ref": format!("#/$defs/{content}") }),
+    };
+    if let Some(doc) = ty.doc() {
+        schema["description"] = json!(doc);
+    }
+    schema
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn struct_schema_marks_optional_members_not_required() {
+        let mut content = IndexMap::new();
+        content.insert(
+            "name".to_string(),
+            StructContent {
+                index: 0,
+                content: Box::new(Type::String { doc: None }),
+            },
+        );
+        content.insert(
+            "nickname".to_string(),
+            StructContent {
+                index: 1,
+                content: Box::new(Type::Optional {
+                    doc: None,
+                    content: Box::new(Type::String { doc: None }),
+                }),
+            },
+        );
+        let schema = type_to_schema(&Type::Struct {
+            doc: Some("a person".to_string()),
+            content,
+        });
+        assert_eq!("a person", schema["description"]);
+        assert_eq!(json!(["name"]), schema["required"]);
+        assert_eq!(
+            json!([{ "type": "string" }, { "type": "null" }]),
+            schema["properties"]["nickname"]["anyOf"]
+        );
+    }
+
+    #[test]
+    fn named_type_becomes_ref() {
+        let schema = type_to_schema(&Type::NamedType {
+            doc: None,
+            content: "Person".to_string(),
+        });
+        assert_eq!("#/$defs/Person", schema["$ref"]);
+    }
+}
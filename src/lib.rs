@@ -6,14 +6,27 @@
 use derive_more::*;
 use indexmap::*;
 
+mod codec;
+mod diff;
+mod format;
+mod json_schema;
+mod validate;
+pub use codec::CodecError;
+pub use diff::{Change, ChangeCategory, SpecDiff};
+pub use format::Format;
+pub use validate::ValidationError;
+
 /// Re-exported dependencies.
 pub mod dependencies {
+    pub use ::base64;
     pub use ::derive_more;
     pub use ::indexmap;
     pub use ::nanoid;
     pub use ::once_cell;
+    pub use ::rmp;
     pub use ::serde;
     pub use ::serde_json;
+    pub use ::serde_yaml;
 }
 
 /// Newtype for a nanoid string.
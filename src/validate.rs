@@ -0,0 +1,404 @@
+//! Semantic validation of an [`IApiSpec`], beyond what serde deserialization checks.
+
+use crate::*;
+use derive_more::Display;
+use std::collections::HashSet;
+
+/// A single problem found while validating an [`IApiSpec`].
+///
+/// Validation collects every problem it finds rather than stopping at the
+/// first, so callers can report (or fix) all of them at once.
+#[derive(Debug, Clone, PartialEq, Eq, Display)]
+pub enum ValidationError {
+    /// A reference named a type that does not exist in `types`.
+    #[display(fmt = "`{path}` references unknown type `{name}`")]
+    UnknownType {
+        /// dotted path to the offending reference
+        path: String,
+        /// the type name that could not be resolved
+        name: String,
+    },
+
+    /// A `Call.feature` named a feature that does not exist in `features` or
+    /// `unstable_features`.
+    #[display(fmt = "`{path}` references unknown feature `{name}`")]
+    UnknownFeature {
+        /// dotted path to the offending reference
+        path: String,
+        /// the feature name that could not be resolved
+        name: String,
+    },
+
+    /// Two or more members of a `Struct`, `Tuple`, or `Enum` share the same
+    /// `StructContent.index`.
+    #[display(fmt = "`{path}` has duplicate index {index}")]
+    DuplicateIndex {
+        /// dotted path to the offending member
+        path: String,
+        /// the index that was reused
+        index: u32,
+    },
+
+    /// An `Enum`'s member indices are not a contiguous `0..n` range, which is
+    /// required since the index is serialized as the wire discriminant.
+    #[display(fmt = "`{path}` indices are not a contiguous 0..n range")]
+    NonContiguousEnum {
+        /// dotted path to the offending enum type
+        path: String,
+    },
+
+    /// Following a type's chain of direct `NamedType` indirection never
+    /// reaches a non-alias type: the chain loops back on itself, or feeds
+    /// into such a loop, without ever passing through an
+    /// `Array`/`Optional`/`Struct`/`Enum`.
+    #[display(fmt = "`{path}` is part of a cyclic type alias chain")]
+    CyclicAlias {
+        /// dotted path to a type in the cycle, or feeding into it
+        path: String,
+    },
+
+    /// A `Feature.stablized_revision` is greater than the spec's own
+    /// `revision`, i.e. it claims to have stabilized in the future.
+    #[display(fmt = "`{path}` stablized_revision {revision} is greater than spec revision")]
+    FeatureRevisionTooNew {
+        /// dotted path to the offending feature
+        path: String,
+        /// the revision the feature claims to have stabilized at
+        revision: u32,
+    },
+}
+
+impl std::error::Error for ValidationError {}
+
+impl IApiSpec {
+    /// Run a structural validation pass over this spec, collecting every
+    /// problem found rather than bailing on the first.
+    ///
+    /// This verifies that every type/feature reference resolves, that
+    /// `Struct`/`Tuple`/`Enum` member indices are sane, that there are no
+    /// infinite `NamedType` alias cycles, and that no feature claims to have
+    /// stabilized after the spec's own revision.
+    pub fn validate(&self) -> Result<(), Vec<ValidationError>> {
+        let mut errors = Vec::new();
+
+        self.check_type_ref("error_type", &self.error_type, &mut errors);
+        self.validate_calls(&mut errors);
+        self.validate_features(&mut errors);
+        self.validate_types(&mut errors);
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    fn check_type_ref(&self, path: &str, name: &str, errors: &mut Vec<ValidationError>) {
+        if !self.types.contains_key(name) {
+            errors.push(ValidationError::UnknownType {
+                path: path.to_string(),
+                name: name.to_string(),
+            });
+        }
+    }
+
+    fn validate_calls(&self, errors: &mut Vec<ValidationError>) {
+        for (group, calls) in [("calls_in", &self.calls_in), ("calls_out", &self.calls_out)] {
+            for (name, call) in calls {
+                let base = format!("{group}.{name}");
+                self.check_type_ref(&format!("{base}.input"), &call.input, errors);
+                self.check_type_ref(&format!("{base}.output"), &call.output, errors);
+                if !self.features.contains_key(&call.feature)
+                    && !self.unstable_features.contains_key(&call.feature)
+                {
+                    errors.push(ValidationError::UnknownFeature {
+                        path: format!("{base}.feature"),
+                        name: call.feature.clone(),
+                    });
+                }
+            }
+        }
+    }
+
+    fn validate_features(&self, errors: &mut Vec<ValidationError>) {
+        for (name, feature) in &self.features {
+            if feature.stablized_revision > self.revision {
+                errors.push(ValidationError::FeatureRevisionTooNew {
+                    path: format!("features.{name}"),
+                    revision: feature.stablized_revision,
+                });
+            }
+        }
+    }
+
+    fn validate_types(&self, errors: &mut Vec<ValidationError>) {
+        for (name, ty) in &self.types {
+            self.validate_type(&format!("types.{name}"), ty, errors);
+        }
+        let mut reported = HashSet::new();
+        for name in self.types.keys() {
+            self.validate_alias_chain(name, &mut reported, errors);
+        }
+    }
+
+    fn validate_type(&self, path: &str, ty: &Type, errors: &mut Vec<ValidationError>) {
+        match ty {
+            Type::Optional { content, .. } | Type::Array { content, .. } => {
+                self.validate_type(&format!("{path}.content"), content, errors);
+            }
+            Type::Tuple { content, .. } => {
+                let items = content
+                    .iter()
+                    .enumerate()
+                    .map(|(i, sc)| (i.to_string(), sc));
+                self.validate_struct_contents(path, items, errors, false);
+            }
+            Type::Struct { content, .. } => {
+                let items = content.iter().map(|(k, v)| (k.clone(), v));
+                self.validate_struct_contents(path, items, errors, false);
+            }
+            Type::Enum { content, .. } => {
+                let items = content.iter().map(|(k, v)| (k.clone(), v));
+                self.validate_struct_contents(path, items, errors, true);
+            }
+            Type::NamedType { content, .. } => {
+                self.check_type_ref(path, content, errors);
+            }
+            Type::Null { .. }
+            | Type::Bool { .. }
+            | Type::I32 { .. }
+            | Type::U32 { .. }
+            | Type::I64 { .. }
+            | Type::U64 { .. }
+            | Type::F64 { .. }
+            | Type::Bytes { .. }
+            | Type::String { .. } => {}
+        }
+    }
+
+    fn validate_struct_contents<'a>(
+        &self,
+        path: &str,
+        items: impl Iterator<Item = (String, &'a StructContent)>,
+        errors: &mut Vec<ValidationError>,
+        require_contiguous: bool,
+    ) {
+        let mut seen_indices = HashSet::new();
+        let mut indices = Vec::new();
+        for (member, sc) in items {
+            let member_path = format!("{path}.{member}");
+            if !seen_indices.insert(sc.index) {
+                errors.push(ValidationError::DuplicateIndex {
+                    path: member_path.clone(),
+                    index: sc.index,
+                });
+            }
+            indices.push(sc.index);
+            self.validate_type(&member_path, &sc.content, errors);
+        }
+        if require_contiguous {
+            indices.sort_unstable();
+            let contiguous = indices
+                .iter()
+                .enumerate()
+                .all(|(i, &idx)| i as u32 == idx);
+            if !contiguous {
+                errors.push(ValidationError::NonContiguousEnum {
+                    path: path.to_string(),
+                });
+            }
+        }
+    }
+
+    /// Follow a chain of direct `NamedType -> NamedType -> ...` aliasing
+    /// starting at `start`, reporting a [`ValidationError::CyclicAlias`] for
+    /// every node along the way whose resolution never terminates — both
+    /// the members of the cycle itself, and any feeder nodes that chain
+    /// into one without being part of it (e.g. `A -> B -> C -> B`: `A`
+    /// feeds into the `B`/`C` cycle and never terminates either).
+    ///
+    /// `reported` dedupes across calls for different `start` values so a
+    /// shared cycle isn't reported once per node that feeds into it.
+    fn validate_alias_chain(
+        &self,
+        start: &str,
+        reported: &mut HashSet<String>,
+        errors: &mut Vec<ValidationError>,
+    ) {
+        if reported.contains(start) {
+            return;
+        }
+        let mut chain = vec![start.to_string()];
+        let mut current = start.to_string();
+        let cyclic = loop {
+            match self.types.get(&current) {
+                Some(Type::NamedType { content, .. }) => {
+                    if chain.contains(content) {
+                        break true;
+                    }
+                    chain.push(content.clone());
+                    current = content.clone();
+                }
+                _ => break false,
+            }
+        };
+        if cyclic {
+            for name in chain {
+                if reported.insert(name.clone()) {
+                    errors.push(ValidationError::CyclicAlias {
+                        path: format!("types.{name}"),
+                    });
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn doc_str(doc: &str) -> Option<String> {
+        Some(doc.to_string())
+    }
+
+    fn minimal_spec() -> IApiSpec {
+        let mut types = IndexMap::new();
+        types.insert("Unit".to_string(), Type::Null { doc: None });
+        types.insert(
+            "Err".to_string(),
+            Type::String { doc: doc_str("an error message") },
+        );
+
+        let mut features = IndexMap::new();
+        features.insert(
+            "core".to_string(),
+            Feature {
+                doc: None,
+                stablized_revision: 1,
+                deprecated: None,
+            },
+        );
+
+        let mut calls_in = IndexMap::new();
+        calls_in.insert(
+            "ping".to_string(),
+            Call {
+                doc: None,
+                feature: "core".to_string(),
+                input: "Unit".to_string(),
+                output: "Unit".to_string(),
+            },
+        );
+
+        IApiSpec {
+            id: NanoId::default(),
+            title: "test spec".to_string(),
+            revision: 1,
+            error_type: "Err".to_string(),
+            unique: None,
+            features,
+            unstable_features: IndexMap::new(),
+            types,
+            calls_out: IndexMap::new(),
+            calls_in,
+        }
+    }
+
+    #[test]
+    fn minimal_spec_is_valid() {
+        assert_eq!(Ok(()), minimal_spec().validate());
+    }
+
+    #[test]
+    fn unknown_call_input_is_reported() {
+        let mut spec = minimal_spec();
+        spec.calls_in.get_mut("ping").unwrap().input = "Missing".to_string();
+        let errors = spec.validate().unwrap_err();
+        assert_eq!(
+            vec![ValidationError::UnknownType {
+                path: "calls_in.ping.input".to_string(),
+                name: "Missing".to_string(),
+            }],
+            errors
+        );
+    }
+
+    #[test]
+    fn direct_alias_cycle_is_reported() {
+        let mut spec = minimal_spec();
+        spec.types.insert(
+            "A".to_string(),
+            Type::NamedType { doc: None, content: "B".to_string() },
+        );
+        spec.types.insert(
+            "B".to_string(),
+            Type::NamedType { doc: None, content: "A".to_string() },
+        );
+        let errors = spec.validate().unwrap_err();
+        assert!(errors.contains(&ValidationError::CyclicAlias {
+            path: "types.A".to_string(),
+        }));
+    }
+
+    #[test]
+    fn feeder_into_alias_cycle_is_reported() {
+        // A -> B -> C -> B: B/C form the cycle, A merely feeds into it, but
+        // resolving A never terminates either so it must be reported too.
+        let mut spec = minimal_spec();
+        spec.types.insert(
+            "A".to_string(),
+            Type::NamedType { doc: None, content: "B".to_string() },
+        );
+        spec.types.insert(
+            "B".to_string(),
+            Type::NamedType { doc: None, content: "C".to_string() },
+        );
+        spec.types.insert(
+            "C".to_string(),
+            Type::NamedType { doc: None, content: "B".to_string() },
+        );
+        let errors = spec.validate().unwrap_err();
+        for name in ["A", "B", "C"] {
+            assert!(
+                errors.contains(&ValidationError::CyclicAlias {
+                    path: format!("types.{name}"),
+                }),
+                "expected types.{name} to be reported as cyclic"
+            );
+        }
+    }
+
+    #[test]
+    fn enum_with_gap_is_non_contiguous() {
+        let mut spec = minimal_spec();
+        let mut content = IndexMap::new();
+        content.insert(
+            "A".to_string(),
+            StructContent { index: 0, content: Box::new(Type::Null { doc: None }) },
+        );
+        content.insert(
+            "B".to_string(),
+            StructContent { index: 2, content: Box::new(Type::Null { doc: None }) },
+        );
+        spec.types.insert(
+            "Choice".to_string(),
+            Type::Enum { doc: None, content },
+        );
+        let errors = spec.validate().unwrap_err();
+        assert!(errors.contains(&ValidationError::NonContiguousEnum {
+            path: "types.Choice".to_string(),
+        }));
+    }
+
+    #[test]
+    fn feature_revision_too_new_is_reported() {
+        let mut spec = minimal_spec();
+        spec.features.get_mut("core").unwrap().stablized_revision = 5;
+        let errors = spec.validate().unwrap_err();
+        assert!(errors.contains(&ValidationError::FeatureRevisionTooNew {
+            path: "features.core".to_string(),
+            revision: 5,
+        }));
+    }
+}